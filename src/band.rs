@@ -0,0 +1,483 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use serde::Deserialize;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Mode {
+    #[serde(rename = "")]
+    Unknown,
+    Ft4,
+    Ft8,
+    Ssb,
+    Usb,
+    Lsb,
+    Cw,
+    Fm,
+    Rtty,
+    C4fm,
+    Psk31,
+    Dstar,
+}
+
+impl FromStr for Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_uppercase().as_str() {
+            "FT4" => Ok(Mode::Ft4),
+            "FT8" => Ok(Mode::Ft8),
+            "LSB" => Ok(Mode::Lsb),
+            "USB" => Ok(Mode::Usb),
+            "SSB" => Ok(Mode::Ssb),
+            "CW" => Ok(Mode::Cw),
+            "FM" => Ok(Mode::Fm),
+            "RTTY" => Ok(Mode::Rtty),
+            "C4FM" => Ok(Mode::C4fm),
+            "PSK31" => Ok(Mode::Psk31),
+            "DSTAR" => Ok(Mode::Dstar),
+            _ => Err(anyhow!("unknown mode \"{value}\"")),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Mode::Ft4 => "FT4",
+                Mode::Ft8 => "FT8",
+                Mode::Lsb => "LSB",
+                Mode::Usb => "USB",
+                Mode::Ssb => "SSB",
+                Mode::Cw => "CW",
+                Mode::Fm => "FM",
+                Mode::Rtty => "RTTY",
+                Mode::C4fm => "C4FM",
+                Mode::Psk31 => "PSK31",
+                Mode::Dstar => "DSTAR",
+                Mode::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+/// Which part of a band a frequency falls in, independent of the specific
+/// mode used there. Used to sanity-check a spot's reported `Mode` against
+/// where it actually sits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment {
+    Cw,
+    Digital,
+    Phone,
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Segment::Cw => "CW",
+                Segment::Digital => "digital",
+                Segment::Phone => "phone",
+            }
+        )
+    }
+}
+
+impl Segment {
+    /// Whether `mode` is plausible for a spot reported in this segment.
+    fn allows(&self, mode: &Mode) -> bool {
+        match self {
+            Segment::Cw => matches!(mode, Mode::Cw),
+            Segment::Digital => matches!(mode, Mode::Ft4 | Mode::Ft8 | Mode::Rtty | Mode::Psk31),
+            Segment::Phone => matches!(
+                mode,
+                Mode::Ssb | Mode::Usb | Mode::Lsb | Mode::Fm | Mode::C4fm | Mode::Dstar
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct Frequency(usize);
+
+impl From<usize> for Frequency {
+    fn from(val: usize) -> Self {
+        Self(val)
+    }
+}
+
+impl Frequency {
+    pub fn mhz(&self) -> usize {
+        self.0 / 1_000_000
+    }
+
+    /// Which supported band this frequency falls in, if any.
+    pub fn band(&self) -> Option<Band> {
+        Band::all()
+            .iter()
+            .find(|band| band.frequency_range().contains(self))
+            .copied()
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(Self((value.parse::<f64>()? * 1_000.0).floor() as usize))
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let khz = (self.0 % 1_000_000) / 1_000;
+        let hz = self.0 % 1_000;
+        write!(
+            f,
+            "{}.{:0<3}{}",
+            self.mhz(),
+            khz,
+            if hz == 500 { ".5" } else { "" }
+        )
+    }
+}
+
+/// A single contiguous piece of a band given over to one kind of activity,
+/// e.g. the CW sub-band at the bottom of 40m or an FT8 watering hole.
+struct SubBand {
+    range: RangeInclusive<Frequency>,
+    segment: Segment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Band {
+    B160m,
+    B80m,
+    B60m,
+    B40m,
+    B30m,
+    B20m,
+    B17m,
+    B15m,
+    B12m,
+    B10m,
+    B6m,
+    B2m,
+    B70cm,
+}
+
+impl Band {
+    pub fn all() -> &'static [Band] {
+        &[
+            Band::B160m,
+            Band::B80m,
+            Band::B60m,
+            Band::B40m,
+            Band::B30m,
+            Band::B20m,
+            Band::B17m,
+            Band::B15m,
+            Band::B12m,
+            Band::B10m,
+            Band::B6m,
+            Band::B2m,
+            Band::B70cm,
+        ]
+    }
+
+    pub fn frequency_range(&self) -> RangeInclusive<Frequency> {
+        match self {
+            Band::B160m => Frequency(1_800_000)..=Frequency(2_000_000),
+            Band::B80m => Frequency(3_500_000)..=Frequency(4_000_000),
+            Band::B60m => Frequency(5_330_500)..=Frequency(5_403_500),
+            Band::B40m => Frequency(7_000_000)..=Frequency(7_300_000),
+            Band::B30m => Frequency(10_100_000)..=Frequency(10_150_000),
+            Band::B20m => Frequency(14_000_000)..=Frequency(14_350_000),
+            Band::B17m => Frequency(18_068_000)..=Frequency(18_168_000),
+            Band::B15m => Frequency(21_000_000)..=Frequency(21_450_000),
+            Band::B12m => Frequency(24_890_000)..=Frequency(24_990_000),
+            Band::B10m => Frequency(28_000_000)..=Frequency(29_700_000),
+            Band::B6m => Frequency(50_000_000)..=Frequency(54_000_000),
+            Band::B2m => Frequency(144_000_000)..=Frequency(148_000_000),
+            Band::B70cm => Frequency(420_000_000)..=Frequency(450_000_000),
+        }
+    }
+
+    /// Sub-bands given over to a particular kind of activity, narrowest
+    /// (and most specific) first. A frequency not covered by any of these
+    /// falls back to the band's general phone allocation.
+    fn sub_bands(&self) -> &'static [SubBand] {
+        match self {
+            Band::B160m => &[SubBand {
+                range: Frequency(1_800_000)..=Frequency(1_840_000),
+                segment: Segment::Cw,
+            }],
+            Band::B80m => &[
+                SubBand {
+                    range: Frequency(3_572_000)..=Frequency(3_574_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(3_574_000)..=Frequency(3_576_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(3_500_000)..=Frequency(3_600_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B60m => &[],
+            Band::B40m => &[
+                SubBand {
+                    range: Frequency(7_073_000)..=Frequency(7_075_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(7_046_500)..=Frequency(7_048_500),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(7_000_000)..=Frequency(7_125_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B30m => &[
+                SubBand {
+                    range: Frequency(10_135_000)..=Frequency(10_137_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(10_139_000)..=Frequency(10_141_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(10_100_000)..=Frequency(10_130_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B20m => &[
+                SubBand {
+                    range: Frequency(14_073_000)..=Frequency(14_075_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(14_079_000)..=Frequency(14_081_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(14_000_000)..=Frequency(14_150_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B17m => &[
+                SubBand {
+                    range: Frequency(18_099_000)..=Frequency(18_101_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(18_103_000)..=Frequency(18_105_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(18_068_000)..=Frequency(18_095_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B15m => &[
+                SubBand {
+                    range: Frequency(21_073_000)..=Frequency(21_075_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(21_139_000)..=Frequency(21_141_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(21_000_000)..=Frequency(21_200_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B12m => &[
+                SubBand {
+                    range: Frequency(24_914_000)..=Frequency(24_916_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(24_918_000)..=Frequency(24_920_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(24_890_000)..=Frequency(24_915_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B10m => &[
+                SubBand {
+                    range: Frequency(28_073_000)..=Frequency(28_075_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(28_179_000)..=Frequency(28_181_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(28_000_000)..=Frequency(28_300_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B6m => &[
+                SubBand {
+                    range: Frequency(50_312_000)..=Frequency(50_314_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(50_317_000)..=Frequency(50_319_000),
+                    segment: Segment::Digital,
+                },
+                SubBand {
+                    range: Frequency(50_000_000)..=Frequency(50_100_000),
+                    segment: Segment::Cw,
+                },
+            ],
+            Band::B2m => &[SubBand {
+                range: Frequency(144_000_000)..=Frequency(144_100_000),
+                segment: Segment::Cw,
+            }],
+            Band::B70cm => &[],
+        }
+    }
+
+    /// Which segment (CW/digital/phone) `frequency` falls in, assuming it
+    /// is within this band. Anything not covered by a known sub-band is
+    /// assumed to be phone, matching how most bands are laid out.
+    pub fn segment(&self, frequency: &Frequency) -> Segment {
+        self.sub_bands()
+            .iter()
+            .find(|sub_band| sub_band.range.contains(frequency))
+            .map(|sub_band| sub_band.segment)
+            .unwrap_or(Segment::Phone)
+    }
+
+    /// Whether `mode` is a plausible mode to hear at `frequency`, assuming
+    /// it falls within this band.
+    pub fn mode_is_plausible(&self, frequency: &Frequency, mode: &Mode) -> bool {
+        if *mode == Mode::Unknown {
+            return true;
+        }
+
+        self.segment(frequency).allows(mode)
+    }
+}
+
+impl FromStr for Band {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "160m" => Ok(Band::B160m),
+            "80m" => Ok(Band::B80m),
+            "60m" => Ok(Band::B60m),
+            "40m" => Ok(Band::B40m),
+            "30m" => Ok(Band::B30m),
+            "20m" => Ok(Band::B20m),
+            "17m" => Ok(Band::B17m),
+            "15m" => Ok(Band::B15m),
+            "12m" => Ok(Band::B12m),
+            "10m" => Ok(Band::B10m),
+            "6m" => Ok(Band::B6m),
+            "2m" => Ok(Band::B2m),
+            "70cm" => Ok(Band::B70cm),
+            _ => Err(anyhow!("unknown band \"{value}\"")),
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Band::B160m => "160m",
+                Band::B80m => "80m",
+                Band::B60m => "60m",
+                Band::B40m => "40m",
+                Band::B30m => "30m",
+                Band::B20m => "20m",
+                Band::B17m => "17m",
+                Band::B15m => "15m",
+                Band::B12m => "12m",
+                Band::B10m => "10m",
+                Band::B6m => "6m",
+                Band::B2m => "2m",
+                Band::B70cm => "70cm",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_reverse_lookup_finds_containing_band() {
+        // `Frequency::from_str` takes kHz, matching the cluster convention
+        // used elsewhere (e.g. `dx::parse_dx_line`'s `frequency_khz`).
+        let freq: Frequency = "14074.000".parse().unwrap();
+        assert_eq!(freq.band(), Some(Band::B20m));
+    }
+
+    #[test]
+    fn frequency_outside_any_band_has_no_band() {
+        let freq: Frequency = "13000.000".parse().unwrap();
+        assert_eq!(freq.band(), None);
+    }
+
+    #[test]
+    fn segment_classifies_known_digital_watering_hole() {
+        let freq: Frequency = "14074.000".parse().unwrap();
+        assert_eq!(Band::B20m.segment(&freq), Segment::Digital);
+    }
+
+    #[test]
+    fn segment_tolerates_typical_audio_offset_around_digital_watering_hole() {
+        // Real FT8 spots are rarely bit-for-bit on 14074.000; a few hundred
+        // Hz of audio-offset variance is routine and shouldn't get
+        // misclassified as CW/phone.
+        let freq: Frequency = "14074.900".parse().unwrap();
+        assert_eq!(Band::B20m.segment(&freq), Segment::Digital);
+    }
+
+    #[test]
+    fn segment_classifies_cw_sub_band() {
+        let freq: Frequency = "14030.000".parse().unwrap();
+        assert_eq!(Band::B20m.segment(&freq), Segment::Cw);
+    }
+
+    #[test]
+    fn segment_falls_back_to_phone_outside_known_sub_bands() {
+        let freq: Frequency = "14300.000".parse().unwrap();
+        assert_eq!(Band::B20m.segment(&freq), Segment::Phone);
+    }
+
+    #[test]
+    fn mode_is_plausible_rejects_ssb_in_cw_sub_band() {
+        let freq: Frequency = "14030.000".parse().unwrap();
+        assert!(!Band::B20m.mode_is_plausible(&freq, &Mode::Ssb));
+    }
+
+    #[test]
+    fn mode_is_plausible_always_allows_unknown_mode() {
+        let freq: Frequency = "14030.000".parse().unwrap();
+        assert!(Band::B20m.mode_is_plausible(&freq, &Mode::Unknown));
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use seabird::Client;
+use seabird::proto::ChannelSource;
+use serde::Deserialize;
+
+use crate::band::Band;
+use crate::config::Config;
+use crate::reply::with_reply;
+
+/// A callsign lookup result, normalized across whichever upstream answered
+/// it. Fields an upstream doesn't provide (e.g. HamQTH doesn't track
+/// license class) are left `None`/empty rather than guessed at.
+#[derive(Clone, Debug)]
+pub struct CallsignInfo {
+    pub callsign: String,
+    pub name: String,
+    pub license_class: String,
+    pub grid: Option<String>,
+    pub expires: Option<String>,
+    pub trustee: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookCurrent {
+    callsign: String,
+    #[serde(rename = "operClass")]
+    oper_class: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookLocation {
+    gridsquare: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookOtherInfo {
+    #[serde(rename = "expiryDate")]
+    expiry_date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookTrustee {
+    callsign: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallookResponse {
+    status: String,
+    current: Option<CallookCurrent>,
+    name: Option<String>,
+    location: Option<CallookLocation>,
+    #[serde(rename = "otherInfo")]
+    other_info: Option<CallookOtherInfo>,
+    trustee: Option<CallookTrustee>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HamqthSession {
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HamqthSessionResponse {
+    session: HamqthSession,
+}
+
+#[derive(Debug, Deserialize)]
+struct HamqthSearch {
+    callsign: String,
+    #[serde(default)]
+    nick: Option<String>,
+    #[serde(default)]
+    grid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HamqthSearchResponse {
+    search: Option<HamqthSearch>,
+}
+
+/// Looks up `callsign`, trying callook.info (US calls, no account needed)
+/// first and falling back to HamQTH if it's configured and callook comes
+/// up empty or its request fails.
+pub async fn lookup_callsign(config: &Config, callsign: &str) -> Result<Option<CallsignInfo>> {
+    match callook_lookup(&config.callook_url, callsign).await {
+        Ok(Some(info)) => return Ok(Some(info)),
+        Ok(None) => {}
+        Err(err) => {
+            // callook hiccup (network blip, non-JSON error page, etc.) —
+            // fall through to HamQTH rather than treating it the same as
+            // a genuine not-found.
+            eprintln!("callook lookup failed for {callsign}: {err:#}");
+        }
+    }
+
+    hamqth_lookup(config, callsign).await
+}
+
+async fn callook_lookup(base_url: &str, callsign: &str) -> Result<Option<CallsignInfo>> {
+    let url = format!("{base_url}/{callsign}/json");
+    let response: CallookResponse = reqwest::get(&url).await?.json().await?;
+
+    if response.status != "VALID" {
+        return Ok(None);
+    }
+
+    let current = response
+        .current
+        .ok_or_else(|| anyhow!("callook response for {callsign} missing current license info"))?;
+
+    Ok(Some(CallsignInfo {
+        callsign: current.callsign,
+        name: response.name.unwrap_or_default(),
+        license_class: current.oper_class,
+        grid: response
+            .location
+            .map(|location| location.gridsquare)
+            .filter(|grid| !grid.is_empty()),
+        expires: response.other_info.map(|info| info.expiry_date),
+        trustee: response
+            .trustee
+            .filter(|trustee| !trustee.callsign.is_empty())
+            .map(|trustee| format!("{} ({})", trustee.callsign, trustee.name)),
+    }))
+}
+
+/// Builds `base` with `pairs` appended as a percent-encoded query string,
+/// so values like a password containing `&`/`=` don't corrupt the request.
+fn hamqth_url(base: &str, pairs: &[(&str, &str)]) -> Result<reqwest::Url> {
+    let mut url = reqwest::Url::parse(base)?;
+    url.query_pairs_mut().extend_pairs(pairs);
+    Ok(url)
+}
+
+/// HamQTH doesn't expose license class, so results from here are sparser
+/// than callook's. Only attempted if `hamqth_username`/`hamqth_password`
+/// are configured, since lookups require a logged-in session.
+async fn hamqth_lookup(config: &Config, callsign: &str) -> Result<Option<CallsignInfo>> {
+    let (Some(username), Some(password)) = (&config.hamqth_username, &config.hamqth_password)
+    else {
+        return Ok(None);
+    };
+
+    let login_url = hamqth_url(&config.hamqth_url, &[("u", username), ("p", password)])?;
+    let login: HamqthSessionResponse = reqwest::get(login_url).await?.json().await?;
+    let Some(session_id) = login.session.session_id else {
+        return Ok(None);
+    };
+
+    let query_url = hamqth_url(
+        &config.hamqth_url,
+        &[
+            ("id", session_id.as_str()),
+            ("callsign", callsign),
+            ("prg", "seabird-ham"),
+        ],
+    )?;
+    let response: HamqthSearchResponse = reqwest::get(query_url).await?.json().await?;
+
+    let Some(search) = response.search else {
+        return Ok(None);
+    };
+
+    Ok(Some(CallsignInfo {
+        callsign: search.callsign,
+        name: search.nick.unwrap_or_default(),
+        license_class: String::new(),
+        grid: search.grid,
+        expires: None,
+        trustee: None,
+    }))
+}
+
+/// Which bands a license class grants. Our `Band` table doesn't model
+/// intra-band slices (e.g. Extra-only segments of 80/40/20m), so this is
+/// necessarily approximate: Technicians get their VHF/UHF/10m allocation,
+/// everyone else is treated as having access to the whole table.
+fn bands_for_class(license_class: &str) -> Vec<Band> {
+    if license_class.eq_ignore_ascii_case("technician") {
+        vec![Band::B10m, Band::B6m, Band::B2m, Band::B70cm]
+    } else {
+        Band::all().to_vec()
+    }
+}
+
+fn format_callsign_info(info: &CallsignInfo) -> String {
+    let mut parts = vec![format!("{} - {}", info.callsign, info.name)];
+
+    if !info.license_class.is_empty() {
+        let bands = bands_for_class(&info.license_class)
+            .iter()
+            .map(|band| band.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        parts.push(format!("class: {}", info.license_class));
+        parts.push(format!("bands: {}", bands));
+    }
+
+    if let Some(grid) = &info.grid {
+        parts.push(format!("grid: {}", grid));
+    }
+
+    if let Some(expires) = &info.expires {
+        parts.push(format!("expires: {}", expires));
+    }
+
+    if let Some(trustee) = &info.trustee {
+        parts.push(format!("trustee: {}", trustee));
+    }
+
+    parts.join(", ")
+}
+
+pub async fn handle_call(
+    client: &mut Client,
+    config: &Config,
+    arg: &str,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let callsign = arg.trim().to_uppercase();
+    if callsign.is_empty() {
+        client
+            .send_message(
+                command_source.channel_id.clone(),
+                with_reply(&command_source, "usage: call <callsign>".to_string()),
+                /* tags = */ None,
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let reply = match lookup_callsign(config, &callsign).await {
+        Ok(Some(info)) => format_callsign_info(&info),
+        Ok(None) => format!("no record for {}", callsign),
+        Err(err) => {
+            eprintln!("failed to look up callsign {}: {:#}", callsign, err);
+            format!("no record for {}", callsign)
+        }
+    };
+
+    client
+        .send_message(
+            command_source.channel_id.clone(),
+            with_reply(&command_source, reply),
+            /* tags = */ None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// A URL nothing is listening on, so a request to it fails immediately
+    /// instead of hanging.
+    fn unreachable_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{addr}")
+    }
+
+    /// Spins up a tiny one-shot-per-request HTTP server on localhost that
+    /// replies with `bodies` in order, one per accepted connection.
+    fn serve_json_responses(bodies: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for body in bodies {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    fn hamqth_config(callook_url: String, hamqth_url: String) -> Config {
+        Config {
+            callook_url,
+            hamqth_url,
+            hamqth_username: Some("user".to_string()),
+            hamqth_password: Some("pass".to_string()),
+            ..Config::default()
+        }
+    }
+
+    const HAMQTH_LOGIN_BODY: &str = r#"{"session":{"session_id":"abc123"}}"#;
+    const HAMQTH_SEARCH_BODY: &str =
+        r#"{"search":{"callsign":"W1AW","nick":"Hiram","grid":"FN31pr"}}"#;
+
+    #[tokio::test]
+    async fn lookup_callsign_falls_back_to_hamqth_when_callook_is_unreachable() {
+        let config = hamqth_config(
+            unreachable_url(),
+            serve_json_responses(vec![HAMQTH_LOGIN_BODY, HAMQTH_SEARCH_BODY]),
+        );
+
+        let info = lookup_callsign(&config, "W1AW").await.unwrap().unwrap();
+
+        assert_eq!(info.callsign, "W1AW");
+        assert_eq!(info.name, "Hiram");
+    }
+
+    #[tokio::test]
+    async fn lookup_callsign_falls_back_to_hamqth_on_genuine_callook_not_found() {
+        let config = hamqth_config(
+            serve_json_responses(vec![r#"{"status":"INVALID"}"#]),
+            serve_json_responses(vec![HAMQTH_LOGIN_BODY, HAMQTH_SEARCH_BODY]),
+        );
+
+        let info = lookup_callsign(&config, "W1AW").await.unwrap().unwrap();
+
+        assert_eq!(info.callsign, "W1AW");
+        assert_eq!(info.name, "Hiram");
+    }
+
+    #[tokio::test]
+    async fn lookup_callsign_returns_none_when_hamqth_not_configured() {
+        let config = Config {
+            callook_url: serve_json_responses(vec![r#"{"status":"INVALID"}"#]),
+            ..Config::default()
+        };
+
+        let info = lookup_callsign(&config, "W1AW").await.unwrap();
+
+        assert!(info.is_none());
+    }
+
+    #[test]
+    fn hamqth_url_percent_encodes_special_characters() {
+        let url = hamqth_url(
+            "https://www.hamqth.com/xml.php",
+            &[("u", "user"), ("p", "p@ss&word=1")],
+        )
+        .unwrap();
+
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("u".to_string(), "user".to_string()),
+                ("p".to_string(), "p@ss&word=1".to_string()),
+            ]
+        );
+    }
+}
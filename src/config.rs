@@ -0,0 +1,147 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de::Error as _;
+
+use crate::band::Mode;
+
+const CONFIG_PATH_VAR: &str = "SEABIRD_HAM_CONFIG";
+const CONFIG_FILE_NAME: &str = "seabird-ham/config.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub solar_url: String,
+    pub pota_url: String,
+    pub callook_url: String,
+    pub hamqth_url: String,
+    pub hamqth_username: Option<String>,
+    pub hamqth_password: Option<String>,
+    pub dx_cluster_host: String,
+    pub dx_cluster_port: u16,
+    pub dx_cluster_callsign: Option<String>,
+    pub default_mode: Mode,
+    pub max_errors_in_row: Option<usize>,
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub max_backoff: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            solar_url: "https://www.hamqsl.com/solarxml.php".to_string(),
+            pota_url: "https://api.pota.app/v1/spots".to_string(),
+            callook_url: "https://callook.info".to_string(),
+            hamqth_url: "https://www.hamqth.com/xml.php".to_string(),
+            hamqth_username: None,
+            hamqth_password: None,
+            dx_cluster_host: "dxc.nc7j.com".to_string(),
+            dx_cluster_port: 7373,
+            dx_cluster_callsign: None,
+            default_mode: Mode::Ssb,
+            max_errors_in_row: Some(5),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+impl Config {
+    /// Loads config from `$SEABIRD_HAM_CONFIG`, falling back to
+    /// `$XDG_CONFIG_HOME/seabird-ham/config.toml`. A missing file is not
+    /// an error: it just means every setting takes its default.
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .with_context(|| format!("failed to parse config at {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("failed to read config at {}", path.display()))
+            }
+        }
+    }
+}
+
+fn config_path() -> Result<PathBuf> {
+    if let Ok(path) = env::var(CONFIG_PATH_VAR) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| anyhow!("could not determine XDG config directory"))?;
+    Ok(config_dir.join(CONFIG_FILE_NAME))
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    parse_duration(&raw).map_err(D::Error::custom)
+}
+
+/// Parses durations like `"30s"` or `"5m"`. Supported units are `s`
+/// (seconds), `m` (minutes), and `h` (hours).
+fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let split_at = raw
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("duration \"{raw}\" is missing a unit (e.g. \"30s\")"))?;
+    let (value, unit) = raw.split_at(split_at);
+
+    let value: u64 = value
+        .parse()
+        .with_context(|| format!("invalid duration value in \"{raw}\""))?;
+
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        _ => bail!("unknown duration unit \"{unit}\" in \"{raw}\""),
+    };
+
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_supports_seconds_minutes_and_hours() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    }
+
+    #[test]
+    fn parse_duration_rejects_missing_unit() {
+        assert!(parse_duration("30").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_unknown_unit() {
+        assert!(parse_duration("30d").is_err());
+    }
+
+    #[test]
+    fn deserialize_duration_parses_toml_string() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(deserialize_with = "deserialize_duration")]
+            value: Duration,
+        }
+
+        let wrapper: Wrapper = toml::from_str("value = \"10m\"").unwrap();
+        assert_eq!(wrapper.value, Duration::from_secs(600));
+    }
+}
@@ -0,0 +1,516 @@
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::NaiveTime;
+use chrono::Utc;
+use seabird::Client;
+use seabird::proto::ChannelSource;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tokio::time::Instant;
+
+use crate::band::Band;
+use crate::band::Frequency;
+use crate::band::Mode;
+use crate::config::Config;
+use crate::reply::with_reply;
+use crate::watch::WatchMessage;
+use crate::watch::WatchRequest;
+
+/// A subscription goes stale and is dropped after this long without a
+/// matching spot, same rationale as the POTA watcher.
+const EXPIRE_AFTER: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How long to wait before retrying after the cluster connection drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How many past spots to keep in memory so `dx <band> [mode]` has
+/// something to answer immediately, instead of only ever reacting to
+/// whatever comes in after the command is run.
+const RECENT_SPOT_CAPACITY: usize = 200;
+
+/// A single DX spot parsed off the cluster feed.
+#[derive(Clone, Debug)]
+pub struct DxSpot {
+    pub spotter: String,
+    pub spotted: String,
+    pub frequency: Frequency,
+    pub comment: String,
+    pub mode: Mode,
+    pub spot_time: DateTime<Utc>,
+}
+
+impl DxSpot {
+    /// Whether this spot matches `band` (`None` meaning "any supported
+    /// band") and `mode`.
+    pub fn matches(&self, band: Option<&Band>, mode: &Mode) -> bool {
+        let on_band = match band {
+            Some(band) => band.frequency_range().contains(&self.frequency),
+            None => self.frequency.band().is_some(),
+        };
+
+        on_band && &self.mode == mode
+    }
+}
+
+pub fn format_dx_spot(spot: &DxSpot) -> String {
+    format!(
+        "DX de {}: {}MHz {} {} ({})",
+        spot.spotter, spot.frequency, spot.spotted, spot.comment, spot.mode
+    )
+}
+
+/// Parses a single `DX de ...` line off the cluster feed, e.g.:
+///
+/// ```text
+/// DX de W1AW-#:    14195.0  JA1ABC       FT8                          1234Z
+/// ```
+///
+/// Cluster software varies a lot in column widths and spacing, so this
+/// only relies on token order, not fixed offsets, and tolerates a missing
+/// or malformed time/mode by falling back to "now"/unknown.
+fn parse_dx_line(line: &str) -> Option<DxSpot> {
+    let rest = line.trim_start().strip_prefix("DX de")?;
+    let (spotter, rest) = rest.split_once(':')?;
+    let spotter = spotter.trim().to_string();
+
+    let mut tokens = rest.split_whitespace();
+    let frequency_khz: f64 = tokens.next()?.parse().ok()?;
+    let spotted = tokens.next()?.to_string();
+
+    let remaining: Vec<&str> = tokens.collect();
+    let (time_token, comment_tokens) = match remaining.split_last() {
+        Some((last, rest)) if is_cluster_time(last) => (Some(*last), rest),
+        _ => (None, remaining.as_slice()),
+    };
+
+    let comment = comment_tokens.join(" ");
+    let mode = comment
+        .split_whitespace()
+        .find_map(|token| token.parse::<Mode>().ok())
+        .unwrap_or(Mode::Unknown);
+    let spot_time = time_token
+        .and_then(parse_cluster_time)
+        .unwrap_or_else(Utc::now);
+
+    Some(DxSpot {
+        spotter,
+        spotted,
+        frequency: ((frequency_khz * 1_000.0).floor() as usize).into(),
+        comment,
+        mode,
+        spot_time,
+    })
+}
+
+/// Whether `token` looks like a cluster timestamp, e.g. `"1234Z"`.
+fn is_cluster_time(token: &str) -> bool {
+    token.len() == 5 && token.ends_with('Z') && token[..4].bytes().all(|b| b.is_ascii_digit())
+}
+
+fn parse_cluster_time(token: &str) -> Option<DateTime<Utc>> {
+    let time = NaiveTime::parse_from_str(&token[..4], "%H%M").ok()?;
+    Some(Utc::now().date_naive().and_time(time).and_utc())
+}
+
+struct Subscription {
+    channel_id: String,
+    band: Option<Band>,
+    mode: Mode,
+    last_activity: Instant,
+}
+
+/// A handle to the background cluster client, held by `main` and passed
+/// into the `dx` command handler.
+pub struct DxClient {
+    requests: mpsc::UnboundedSender<WatchRequest>,
+    recent: Arc<Mutex<VecDeque<DxSpot>>>,
+}
+
+/// Spawns the background DX cluster client, unless `dx_cluster_callsign`
+/// isn't configured, in which case the feature is simply disabled.
+pub fn spawn(config: &Config, messages: mpsc::UnboundedSender<WatchMessage>) -> Option<DxClient> {
+    let callsign = config.dx_cluster_callsign.clone()?;
+    let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+    let recent = Arc::new(Mutex::new(VecDeque::new()));
+
+    tokio::spawn(run(
+        config.dx_cluster_host.clone(),
+        config.dx_cluster_port,
+        callsign,
+        requests_rx,
+        messages,
+        recent.clone(),
+    ));
+
+    Some(DxClient {
+        requests: requests_tx,
+        recent,
+    })
+}
+
+/// Owns the persistent connection to the cluster, reconnecting with a
+/// fixed delay whenever it drops. Subscriptions live across reconnects.
+async fn run(
+    host: String,
+    port: u16,
+    callsign: String,
+    mut requests: mpsc::UnboundedReceiver<WatchRequest>,
+    messages: mpsc::UnboundedSender<WatchMessage>,
+    recent: Arc<Mutex<VecDeque<DxSpot>>>,
+) {
+    let mut subscriptions: Vec<Subscription> = Vec::new();
+
+    loop {
+        match connect(&host, port, &callsign).await {
+            Ok(stream) => {
+                run_connection(
+                    stream,
+                    &mut requests,
+                    &messages,
+                    &recent,
+                    &mut subscriptions,
+                )
+                .await;
+            }
+            Err(err) => {
+                eprintln!("failed to connect to DX cluster {host}:{port}: {err:#}");
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn connect(host: &str, port: u16, callsign: &str) -> Result<BufReader<TcpStream>> {
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("connecting to {host}:{port}"))?;
+
+    stream
+        .write_all(format!("{callsign}\r\n").as_bytes())
+        .await?;
+
+    Ok(BufReader::new(stream))
+}
+
+/// Reads lines from a single connection until it drops, matching `DX de`
+/// spots against live subscriptions and recording them in `recent`.
+/// Returns (rather than erroring out) once the connection is lost, so the
+/// caller can reconnect.
+async fn run_connection(
+    mut stream: BufReader<TcpStream>,
+    requests: &mut mpsc::UnboundedReceiver<WatchRequest>,
+    messages: &mpsc::UnboundedSender<WatchMessage>,
+    recent: &Arc<Mutex<VecDeque<DxSpot>>>,
+    subscriptions: &mut Vec<Subscription>,
+) {
+    let mut line = String::new();
+    let mut prune_tick = tokio::time::interval(EXPIRE_AFTER / 12);
+
+    loop {
+        line.clear();
+
+        tokio::select! {
+            request = requests.recv() => {
+                let Some(request) = request else {
+                    return;
+                };
+
+                subscriptions.push(Subscription {
+                    channel_id: request.channel_id,
+                    band: request.band,
+                    mode: request.mode,
+                    last_activity: Instant::now(),
+                });
+            }
+            _ = prune_tick.tick() => {
+                subscriptions.retain(|subscription| subscription.last_activity.elapsed() < EXPIRE_AFTER);
+            }
+            read = stream.read_line(&mut line) => {
+                match read {
+                    Ok(0) => {
+                        eprintln!("DX cluster connection closed, reconnecting");
+                        return;
+                    }
+                    Ok(_) => {
+                        let Some(spot) = parse_dx_line(line.trim_end()) else {
+                            continue;
+                        };
+
+                        {
+                            let mut recent = recent.lock().unwrap();
+                            recent.push_back(spot.clone());
+                            while recent.len() > RECENT_SPOT_CAPACITY {
+                                recent.pop_front();
+                            }
+                        }
+
+                        for subscription in subscriptions.iter_mut() {
+                            if spot.matches(subscription.band.as_ref(), &subscription.mode) {
+                                subscription.last_activity = Instant::now();
+                                let _ = messages.send(WatchMessage {
+                                    channel_id: subscription.channel_id.clone(),
+                                    text: format!("new spot: {}", format_dx_spot(&spot)),
+                                });
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("error reading from DX cluster: {err:#}");
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn handle_dx_impl(
+    client: &mut Client,
+    dx_client: &DxClient,
+    band_str: &str,
+    mode: Mode,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let band = if band_str.eq_ignore_ascii_case("all") {
+        None
+    } else {
+        match band_str.parse::<Band>() {
+            Ok(band) => Some(band),
+            Err(_) => {
+                client
+                    .send_message(
+                        command_source.channel_id.clone(),
+                        with_reply(&command_source, "invalid band".to_string()),
+                        /* tags = */ None,
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let spot = {
+        let recent = dx_client.recent.lock().unwrap();
+        recent
+            .iter()
+            .rev()
+            .find(|spot| spot.matches(band.as_ref(), &mode))
+            .cloned()
+    };
+
+    let reply = match spot {
+        Some(spot) => format_dx_spot(&spot),
+        None => {
+            let band_desc = band
+                .as_ref()
+                .map(|b| b.to_string())
+                .unwrap_or_else(|| "any band".to_string());
+            format!("no DX spots seen on {band_desc} over {mode}")
+        }
+    };
+
+    client
+        .send_message(
+            command_source.channel_id.clone(),
+            with_reply(&command_source, reply),
+            /* tags = */ None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_dx_watch(
+    client: &mut Client,
+    dx_client: &DxClient,
+    band_str: &str,
+    mode: Mode,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let band = if band_str.eq_ignore_ascii_case("all") {
+        None
+    } else {
+        match band_str.parse::<Band>() {
+            Ok(band) => Some(band),
+            Err(_) => {
+                client
+                    .send_message(
+                        command_source.channel_id.clone(),
+                        with_reply(&command_source, "invalid band".to_string()),
+                        /* tags = */ None,
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let band_desc = band
+        .as_ref()
+        .map(|b| b.to_string())
+        .unwrap_or_else(|| "all bands".to_string());
+
+    // The receiving end lives in the cluster loop; if it's gone the bot is
+    // shutting down, so there's nothing useful to do with the error.
+    let _ = dx_client.requests.send(WatchRequest {
+        channel_id: command_source.channel_id.clone(),
+        band,
+        mode: mode.clone(),
+    });
+
+    client
+        .send_message(
+            command_source.channel_id.clone(),
+            with_reply(
+                &command_source,
+                format!("watching {band_desc} over {mode} for new DX spots"),
+            ),
+            /* tags = */ None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn handle_dx(
+    client: &mut Client,
+    dx_client: Option<&DxClient>,
+    config: &Config,
+    arg: &str,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let Some(dx_client) = dx_client else {
+        client
+            .send_message(
+                command_source.channel_id.clone(),
+                with_reply(&command_source, "DX cluster not configured".to_string()),
+                /* tags = */ None,
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let parts: Vec<_> = arg.split_whitespace().collect();
+    match parts.as_slice() {
+        ["watch", band_str] => {
+            handle_dx_watch(
+                client,
+                dx_client,
+                band_str,
+                config.default_mode.clone(),
+                command_source,
+            )
+            .await?;
+        }
+        ["watch", band_str, mode_str] => {
+            let mode = match mode_str.parse::<Mode>() {
+                Ok(mode) => mode,
+                Err(_) => {
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(&command_source, "invalid mode".to_string()),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            handle_dx_watch(client, dx_client, band_str, mode, command_source).await?;
+        }
+        [band_str] => {
+            handle_dx_impl(
+                client,
+                dx_client,
+                band_str,
+                config.default_mode.clone(),
+                command_source,
+            )
+            .await?;
+        }
+        [band_str, mode_str] => {
+            let mode = match mode_str.parse::<Mode>() {
+                Ok(mode) => mode,
+                Err(_) => {
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(&command_source, "invalid mode".to_string()),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            handle_dx_impl(client, dx_client, band_str, mode, command_source).await?;
+        }
+        _ => {
+            client
+                .send_message(
+                    command_source.channel_id.clone(),
+                    with_reply(
+                        &command_source,
+                        "invalid dx command. Usage: dx <band|all> [mode] | dx watch <band|all> [mode]"
+                            .to_string(),
+                    ),
+                    /* tags = */ None,
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dx_line_extracts_spotter_spotted_frequency_mode_and_comment() {
+        let spot =
+            parse_dx_line("DX de W1AW-#:    14195.0  JA1ABC       FT8 here we go      1234Z")
+                .unwrap();
+
+        assert_eq!(spot.spotter, "W1AW-#");
+        assert_eq!(spot.spotted, "JA1ABC");
+        assert_eq!(spot.frequency.mhz(), 14);
+        assert_eq!(spot.mode, Mode::Ft8);
+        assert_eq!(spot.comment, "FT8 here we go");
+    }
+
+    #[test]
+    fn parse_dx_line_defaults_mode_to_unknown_when_absent() {
+        let spot = parse_dx_line("DX de W1AW-#:    14195.0  JA1ABC       good signal  1234Z").unwrap();
+        assert_eq!(spot.mode, Mode::Unknown);
+    }
+
+    #[test]
+    fn parse_dx_line_tolerates_missing_time() {
+        let spot = parse_dx_line("DX de W1AW-#:    14195.0  JA1ABC       CW").unwrap();
+        assert_eq!(spot.mode, Mode::Cw);
+    }
+
+    #[test]
+    fn parse_dx_line_rejects_non_dx_lines() {
+        assert!(parse_dx_line("hello world").is_none());
+    }
+
+    #[test]
+    fn is_cluster_time_matches_hhmmz() {
+        assert!(is_cluster_time("1234Z"));
+        assert!(!is_cluster_time("1234"));
+        assert!(!is_cluster_time("12Z"));
+    }
+}
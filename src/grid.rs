@@ -0,0 +1,225 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use seabird::Client;
+use seabird::proto::ChannelSource;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::reply::with_reply;
+
+/// Radius of the Earth in km, used for haversine distance.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Width/height of a subsquare cell, in degrees of longitude/latitude.
+const SUBSQUARE_LON_DEG: f64 = 2.0 / 24.0;
+const SUBSQUARE_LAT_DEG: f64 = 1.0 / 24.0;
+
+/// A 6-character Maidenhead grid locator, e.g. `"FN20xr"`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid(String);
+
+impl Grid {
+    /// Encodes a lat/lon pair as a 6-character Maidenhead locator.
+    /// Latitude is clamped to +/-90 and longitude is wrapped into
+    /// [-180, 180) before encoding.
+    pub fn encode(lat: f64, lon: f64) -> Self {
+        let lat = lat.clamp(-90.0, 90.0);
+        let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+
+        let norm_lon = lon + 180.0;
+        let norm_lat = lat + 90.0;
+
+        let field_lon = ((norm_lon / 20.0) as usize).min(17);
+        let field_lat = ((norm_lat / 10.0) as usize).min(17);
+
+        let rem_lon = norm_lon - (field_lon as f64) * 20.0;
+        let rem_lat = norm_lat - (field_lat as f64) * 10.0;
+
+        let square_lon = ((rem_lon / 2.0) as usize).min(9);
+        let square_lat = (rem_lat as usize).min(9);
+
+        let rem_lon = rem_lon - (square_lon as f64) * 2.0;
+        let rem_lat = rem_lat - square_lat as f64;
+
+        let subsquare_lon = ((rem_lon / SUBSQUARE_LON_DEG) as usize).min(23);
+        let subsquare_lat = ((rem_lat / SUBSQUARE_LAT_DEG) as usize).min(23);
+
+        let chars = [
+            (b'A' + field_lon as u8) as char,
+            (b'A' + field_lat as u8) as char,
+            (b'0' + square_lon as u8) as char,
+            (b'0' + square_lat as u8) as char,
+            (b'a' + subsquare_lon as u8) as char,
+            (b'a' + subsquare_lat as u8) as char,
+        ];
+
+        Self(chars.iter().collect())
+    }
+
+    /// Decodes this locator back to the lat/lon of its cell center.
+    fn center(&self) -> (f64, f64) {
+        let chars: Vec<char> = self.0.chars().collect();
+
+        let field_lon = (chars[0].to_ascii_uppercase() as u8 - b'A') as f64;
+        let field_lat = (chars[1].to_ascii_uppercase() as u8 - b'A') as f64;
+        let square_lon = (chars[2] as u8 - b'0') as f64;
+        let square_lat = (chars[3] as u8 - b'0') as f64;
+        let subsquare_lon = (chars[4].to_ascii_lowercase() as u8 - b'a') as f64;
+        let subsquare_lat = (chars[5].to_ascii_lowercase() as u8 - b'a') as f64;
+
+        let lon = field_lon * 20.0 + square_lon * 2.0 + subsquare_lon * SUBSQUARE_LON_DEG - 180.0
+            + SUBSQUARE_LON_DEG / 2.0;
+        let lat = field_lat * 10.0 + square_lat + subsquare_lat * SUBSQUARE_LAT_DEG - 90.0
+            + SUBSQUARE_LAT_DEG / 2.0;
+
+        (lat, lon)
+    }
+
+    /// Great-circle distance to `other`, in km, via the haversine formula.
+    pub fn distance_km(&self, other: &Grid) -> f64 {
+        let (lat1, lon1) = self.center();
+        let (lat2, lon2) = other.center();
+
+        let phi1 = lat1.to_radians();
+        let phi2 = lat2.to_radians();
+        let d_phi = (lat2 - lat1).to_radians();
+        let d_lambda = wrap_degrees(lon2 - lon1).to_radians();
+
+        let a =
+            (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+
+        EARTH_RADIUS_KM * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+
+    /// Initial bearing from this locator to `other`, in degrees, 0-360.
+    pub fn bearing_deg(&self, other: &Grid) -> f64 {
+        let (lat1, lon1) = self.center();
+        let (lat2, lon2) = other.center();
+
+        let phi1 = lat1.to_radians();
+        let phi2 = lat2.to_radians();
+        let d_lambda = wrap_degrees(lon2 - lon1).to_radians();
+
+        let theta = (d_lambda.sin() * phi2.cos())
+            .atan2(phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lambda.cos());
+
+        (theta.to_degrees() + 360.0) % 360.0
+    }
+}
+
+/// Wraps a longitude delta (in degrees) into [-180, 180) so distance and
+/// bearing take the shorter way around the antimeridian.
+fn wrap_degrees(delta: f64) -> f64 {
+    (delta + 180.0).rem_euclid(360.0) - 180.0
+}
+
+impl FromStr for Grid {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let chars: Vec<char> = value.chars().collect();
+        let valid = chars.len() == 6
+            && chars[0].is_ascii_alphabetic()
+            && ('A'..='R').contains(&chars[0].to_ascii_uppercase())
+            && chars[1].is_ascii_alphabetic()
+            && ('A'..='R').contains(&chars[1].to_ascii_uppercase())
+            && chars[2].is_ascii_digit()
+            && chars[3].is_ascii_digit()
+            && chars[4].is_ascii_alphabetic()
+            && ('a'..='x').contains(&chars[4].to_ascii_lowercase())
+            && chars[5].is_ascii_alphabetic()
+            && ('a'..='x').contains(&chars[5].to_ascii_lowercase());
+
+        if !valid {
+            return Err(anyhow!("invalid grid locator \"{value}\""));
+        }
+
+        let canonical = [
+            chars[0].to_ascii_uppercase(),
+            chars[1].to_ascii_uppercase(),
+            chars[2],
+            chars[3],
+            chars[4].to_ascii_lowercase(),
+            chars[5].to_ascii_lowercase(),
+        ];
+
+        Ok(Self(canonical.iter().collect()))
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub async fn handle_grid(
+    client: &mut Client,
+    arg: &str,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let parts: Vec<_> = arg.split_whitespace().collect();
+    let reply = match parts.as_slice() {
+        [lat_str, lon_str] if lat_str.parse::<f64>().is_ok() && lon_str.parse::<f64>().is_ok() => {
+            let lat: f64 = lat_str.parse()?;
+            let lon: f64 = lon_str.parse()?;
+            Grid::encode(lat, lon).to_string()
+        }
+        [first, second] => match (first.parse::<Grid>(), second.parse::<Grid>()) {
+            (Ok(from), Ok(to)) => format!(
+                "{:.0} km, bearing {:.0}°",
+                from.distance_km(&to),
+                from.bearing_deg(&to)
+            ),
+            _ => "invalid grid locator".to_string(),
+        },
+        _ => "usage: grid <lat> <lon> | grid <grid1> <grid2>".to_string(),
+    };
+
+    client
+        .send_message(
+            command_source.channel_id.clone(),
+            with_reply(&command_source, reply),
+            /* tags = */ None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_known_point() {
+        assert_eq!(Grid::encode(41.7147, -72.6853).to_string(), "FN31pr");
+    }
+
+    #[test]
+    fn from_str_is_case_insensitive_and_canonicalizes() {
+        let grid: Grid = "fn31pr".parse().unwrap();
+        assert_eq!(grid.to_string(), "FN31pr");
+    }
+
+    #[test]
+    fn from_str_rejects_malformed_locator() {
+        assert!("FN3".parse::<Grid>().is_err());
+        assert!("ZZ31pr".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn distance_km_is_zero_between_same_locator() {
+        let grid: Grid = "FN31pr".parse().unwrap();
+        assert_eq!(grid.distance_km(&grid), 0.0);
+    }
+
+    #[test]
+    fn distance_and_bearing_match_known_reference_locators() {
+        let hartford: Grid = "FN31pr".parse().unwrap();
+        let london: Grid = "IO91wm".parse().unwrap();
+
+        assert!((hartford.distance_km(&london) - 5415.0).abs() < 5.0);
+        assert!((hartford.bearing_deg(&london) - 52.2).abs() < 1.0);
+    }
+}
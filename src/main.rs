@@ -1,15 +1,23 @@
+mod band;
+mod callsign;
+mod config;
+mod dx;
+mod grid;
+mod pota;
+mod reply;
+mod resilience;
+mod watch;
+
 use anyhow::Result;
 use anyhow::anyhow;
 use anyhow::bail;
-use chrono::DateTime;
-use chrono::NaiveDateTime;
-use chrono::TimeDelta;
-use chrono::TimeZone;
-use chrono::Utc;
+use band::Band;
+use config::Config;
 use futures::StreamExt;
+use resilience::FetchOutcome;
+use resilience::Upstream;
 use seabird::Client;
 use seabird::ClientConfig;
-use seabird::proto::ChannelSource;
 use seabird::proto::CommandEvent;
 use seabird::proto::CommandMetadata;
 use seabird::proto::StreamEventsRequest;
@@ -17,9 +25,7 @@ use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::env;
-use std::fmt;
-use std::ops::RangeInclusive;
-use std::str::FromStr;
+use tokio::sync::mpsc;
 
 #[derive(Debug, Deserialize)]
 struct BandData {
@@ -117,359 +123,62 @@ fn format_solar_data(data: Solar) -> Result<Vec<String>> {
     let mut output: Vec<String> = Vec::new();
     output.push(format!("updated {}", data.solardata.updated));
 
-    for (name, band) in bands {
+    for (name, band) in &bands {
         output.push(format!(
             "{} - day: {}, night: {}",
             name, band.day, band.night
         ));
     }
 
-    Ok(output)
-}
-
-async fn fetch_solar_data() -> Result<Solar> {
-    let text = reqwest::get("https://www.hamqsl.com/solarxml.php")
-        .await?
-        .text()
-        .await?;
-
-    Ok(serde_xml_rs::from_str(&text)?)
-}
-
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "UPPERCASE")]
-enum Mode {
-    #[serde(rename = "")]
-    Unknown,
-    Ft4,
-    Ft8,
-    Ssb,
-    Usb,
-    Lsb,
-    Cw,
-    Fm,
-    Rtty,
-    C4fm,
-    Psk31,
-    Dstar,
-}
-
-impl FromStr for Mode {
-    type Err = anyhow::Error;
-
-    fn from_str(value: &str) -> Result<Self> {
-        match value.to_uppercase().as_str() {
-            "FT4" => Ok(Mode::Ft4),
-            "FT8" => Ok(Mode::Ft8),
-            "LSB" => Ok(Mode::Lsb),
-            "USB" => Ok(Mode::Usb),
-            "SSB" => Ok(Mode::Ssb),
-            "CW" => Ok(Mode::Cw),
-            "FM" => Ok(Mode::Fm),
-            "RTTY" => Ok(Mode::Rtty),
-            "C4FM" => Ok(Mode::C4fm),
-            "PSK31" => Ok(Mode::Psk31),
-            "DSTAR" => Ok(Mode::Dstar),
-            _ => Err(anyhow!("unknown mode \"{value}\"")),
-        }
-    }
-}
-
-impl fmt::Display for Mode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Mode::Ft4 => "FT4",
-                Mode::Ft8 => "FT8",
-                Mode::Lsb => "LSB",
-                Mode::Usb => "USB",
-                Mode::Ssb => "SSB",
-                Mode::Cw => "CW",
-                Mode::Fm => "FM",
-                Mode::Rtty => "RTTY",
-                Mode::C4fm => "C4FM",
-                Mode::Psk31 => "PSK31",
-                Mode::Dstar => "DSTAR",
-                Mode::Unknown => "unknown",
-            }
-        )
-    }
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ParsedActivation {
-    activator: String,
-    name: String,
-    location_desc: String,
-    mode: Mode,
-    frequency: String,
-    spot_time: String,
-}
-
-impl ParsedActivation {
-    fn try_into_activation(self) -> Result<Activation> {
-        Ok(Activation {
-            activator: self.activator,
-            name: self.name,
-            location_desc: self.location_desc,
-            mode: self.mode,
-            frequency: ((self.frequency.parse::<f64>()? * 1_000.0).floor() as usize).into(),
-            spot_time: Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(
-                &self.spot_time,
-                "%Y-%m-%dT%H:%M:%S",
-            )?),
-        })
-    }
-}
-
-#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
-struct Frequency(usize);
-
-impl From<usize> for Frequency {
-    fn from(val: usize) -> Self {
-        Self(val)
-    }
-}
-
-impl Frequency {
-    fn mhz(&self) -> usize {
-        self.0 / 1_000_000
-    }
-}
-
-impl FromStr for Frequency {
-    type Err = anyhow::Error;
-
-    fn from_str(value: &str) -> Result<Self> {
-        Ok(Self((value.parse::<f64>()? * 1_000.0).floor() as usize))
-    }
-}
-
-impl fmt::Display for Frequency {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let khz = (self.0 % 1_000_000) / 1_000;
-        let hz = self.0 % 1_000;
-        write!(
-            f,
-            "{}.{:0<3}{}",
-            self.mhz(),
-            khz,
-            if hz == 500 { ".5" } else { "" }
-        )
-    }
-}
-
-#[derive(Debug)]
-struct Activation {
-    activator: String,
-    name: String,
-    location_desc: String,
-    mode: Mode,
-    frequency: Frequency,
-    spot_time: DateTime<Utc>,
-}
-
-impl Activation {
-    fn age(&self) -> TimeDelta {
-        self.spot_time - Utc::now()
-    }
-}
-
-#[derive(Debug)]
-enum Band {
-    B20m,
-    B40m,
-}
-
-impl FromStr for Band {
-    type Err = anyhow::Error;
-
-    fn from_str(value: &str) -> Result<Self> {
-        match value.to_lowercase().as_str() {
-            "20m" => Ok(Band::B20m),
-            "40m" => Ok(Band::B40m),
-            _ => Err(anyhow!("unknown band \"{value}\"")),
-        }
-    }
-}
-
-impl fmt::Display for Band {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Band::B20m => "20m",
-                Band::B40m => "30m",
-            }
+    let open = open_bands(&bands);
+    output.push(if open.is_empty() {
+        "no supported bands currently reported open".to_string()
+    } else {
+        format!(
+            "open bands: {}",
+            open.iter()
+                .map(|band| band.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
         )
-    }
-}
-
-impl Band {
-    fn frequency_range(&self) -> RangeInclusive<Frequency> {
-        match self {
-            Band::B20m => Frequency(14000000)..=Frequency(14350000),
-            Band::B40m => Frequency(7000000)..=Frequency(7300000),
-        }
-    }
-}
-
-async fn fetch_activations() -> Result<Vec<Activation>> {
-    reqwest::get("https://api.pota.app/v1/spots")
-        .await?
-        .json::<Vec<ParsedActivation>>()
-        .await?
-        .into_iter()
-        .map(|a| a.try_into_activation())
-        .collect::<Result<Vec<Activation>>>()
-}
+    });
 
-fn with_reply(command_source: &ChannelSource, message: String) -> String {
-    format!(
-        "{}{}",
-        command_source
-            .user
-            .as_ref()
-            .map(|u| format!("{}: ", u.display_name))
-            .unwrap_or_else(|| "".to_string()),
-        message
-    )
+    Ok(output)
 }
 
-async fn most_recent_activation(band: &Band, mode: &Mode) -> Result<Option<Activation>> {
-    let activations = fetch_activations().await?;
-    for activation in activations {
-        if band.frequency_range().contains(&activation.frequency) && &activation.mode == mode {
-            return Ok(Some(activation));
+/// Cross-references hamqsl's condition names (e.g. "80m-40m") against our
+/// supported `Band`s to report which of them are reported open right now.
+/// A band counts as open if its daytime condition isn't "Poor".
+fn open_bands(bands: &BTreeMap<String, BandCondition>) -> Vec<Band> {
+    let mut open = Vec::new();
+    for (name, condition) in bands {
+        if condition.day.eq_ignore_ascii_case("poor") {
+            continue;
         }
-    }
-
-    Ok(None)
-}
 
-async fn handle_pota_impl(
-    client: &mut Client,
-    band_str: &str,
-    mode: Mode,
-    command_source: ChannelSource,
-) -> Result<()> {
-    let band = match band_str.parse::<Band>() {
-        Ok(band) => band,
-        Err(_) => {
-            client
-                .send_message(
-                    command_source.channel_id.clone(),
-                    with_reply(&command_source, "invalid_band".to_string()),
-                    /* tags = */ None,
-                )
-                .await?;
-            return Ok(());
-        }
-    };
-
-    match most_recent_activation(&band, &mode).await? {
-        Some(activation) => {
-            let age_string = {
-                let seconds = activation.age().num_seconds().abs();
-                if seconds > 60 {
-                    format!("{}m{}s", seconds / 60, seconds % 60)
-                } else {
-                    seconds.to_string()
+        for part in name.split('-') {
+            if let Ok(band) = part.parse::<Band>() {
+                if !open.contains(&band) {
+                    open.push(band);
                 }
-            };
-
-            client
-                .send_message(
-                    command_source.channel_id.clone(),
-                    with_reply(
-                        &command_source,
-                        format!(
-                            "[time:{},age:{}] {}MHz {}, {} - {} ({})",
-                            activation.spot_time,
-                            age_string,
-                            activation.frequency,
-                            activation.mode,
-                            activation.location_desc,
-                            activation.name,
-                            activation.activator,
-                        ),
-                    ),
-                    /* tags = */ None,
-                )
-                .await?;
-        }
-        None => {
-            client
-                .send_message(
-                    command_source.channel_id.clone(),
-                    with_reply(
-                        &command_source,
-                        format!("no activations found on {} over SSB", band),
-                    ),
-                    /* tags = */ None,
-                )
-                .await?;
+            }
         }
     }
 
-    Ok(())
+    open.sort_by_key(|band| Band::all().iter().position(|b| b == band));
+    open
 }
 
-async fn handle_pota(client: &mut Client, arg: &str, command_source: ChannelSource) -> Result<()> {
-    let parts: Vec<_> = arg.split_whitespace().collect();
-    match parts.as_slice() {
-        [band_str] => {
-            handle_pota_impl(client, band_str, Mode::Ssb, command_source).await?;
-        }
-        [band_str, mode_str] => {
-            let mode = match mode_str.parse::<Mode>() {
-                Ok(mode) => mode,
-                Err(_) => {
-                    client
-                        .send_message(
-                            command_source.channel_id.clone(),
-                            format!(
-                                "{}invalid mode",
-                                command_source
-                                    .user
-                                    .map(|u| format!("{}: ", u.display_name))
-                                    .unwrap_or_else(|| "".to_string())
-                            ),
-                            /* tags = */ None,
-                        )
-                        .await?;
-                    return Ok(());
-                }
-            };
+async fn fetch_solar_data(url: &str) -> Result<Solar> {
+    let text = reqwest::get(url).await?.text().await?;
 
-            handle_pota_impl(client, band_str, mode, command_source).await?;
-        }
-        _ => {
-            client
-                .send_message(
-                    command_source.channel_id.clone(),
-                    format!(
-                        "{}invalid pota command. Usage: pota <band> [mode]",
-                        command_source
-                            .user
-                            .map(|u| format!("{}: ", u.display_name))
-                            .unwrap_or_else(|| "".to_string())
-                    ),
-                    /* tags = */ None,
-                )
-                .await?;
-        }
-    }
-
-    Ok(())
+    Ok(serde_xml_rs::from_str(&text)?)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let config = Config::load()?;
+
     let url = env::var("SEABIRD_URL").unwrap_or_else(|_| "https://api.seabird.chat".to_string());
     println!("connecting with URL {}", url);
 
@@ -488,7 +197,28 @@ async fn main() -> Result<()> {
         CommandMetadata {
             name: "pota".to_string(),
             short_help: "find most recent POTA activation".to_string(),
-            full_help: "find the most recent Parks on the Air activation. Usage: pota <band> [mode]. Default mode is SSB.".to_string(),
+            full_help: format!("find the most recent Parks on the Air activation. Usage: pota <band|all> [mode] | pota watch <band|all> [mode]. Default mode is {}.", config.default_mode),
+        }
+    ), (
+        "grid".to_string(),
+        CommandMetadata {
+            name: "grid".to_string(),
+            short_help: "Maidenhead grid locator conversion and distance".to_string(),
+            full_help: "convert a lat/lon to a Maidenhead grid locator, or compute distance and bearing between two locators. Usage: grid <lat> <lon> | grid <grid1> <grid2>".to_string(),
+        }
+    ), (
+        "call".to_string(),
+        CommandMetadata {
+            name: "call".to_string(),
+            short_help: "look up a callsign's license info".to_string(),
+            full_help: "look up a callsign's name, license class, grid, and bands via callook.info/HamQTH. Usage: call <callsign>".to_string(),
+        }
+    ), (
+        "dx".to_string(),
+        CommandMetadata {
+            name: "dx".to_string(),
+            short_help: "find most recent DX cluster spot".to_string(),
+            full_help: format!("find the most recent DX cluster spot. Usage: dx <band|all> [mode] | dx watch <band|all> [mode]. Default mode is {}.", config.default_mode),
         }
     )]);
 
@@ -498,39 +228,106 @@ async fn main() -> Result<()> {
         .await?
         .into_inner();
 
-    while let Some(event) = stream.next().await.transpose()? {
-        if let Some(seabird::proto::event::Inner::Command(CommandEvent {
-            source: Some(command_source),
-            command,
-            arg,
-        })) = event.inner
-        {
-            if command == "bands" {
-                let output = format_solar_data(fetch_solar_data().await?)?;
-                client
-                    .send_message(
-                        command_source.channel_id.clone(),
-                        match command_source.user {
-                            Some(user) => {
-                                format!("{}: current band conditions:", user.display_name)
+    let (watch_request_tx, watch_request_rx) = mpsc::unbounded_channel();
+    let (watch_message_tx, mut watch_message_rx) = mpsc::unbounded_channel();
+    tokio::spawn(watch::run(
+        watch_request_rx,
+        watch_message_tx.clone(),
+        config.pota_url.clone(),
+    ));
+
+    let dx_client = dx::spawn(&config, watch_message_tx);
+
+    let mut solar_upstream = Upstream::new(config.max_errors_in_row, config.max_backoff);
+    let mut pota_upstream = Upstream::new(config.max_errors_in_row, config.max_backoff);
+
+    loop {
+        tokio::select! {
+            event = stream.next() => {
+                let Some(event) = event.transpose()? else {
+                    break;
+                };
+
+                if let Some(seabird::proto::event::Inner::Command(CommandEvent {
+                    source: Some(command_source),
+                    command,
+                    arg,
+                })) = event.inner
+                {
+                    if command == "bands" {
+                        match solar_upstream.fetch(|| fetch_solar_data(&config.solar_url)).await {
+                            FetchOutcome::Ready(data) => {
+                                let output = format_solar_data(data)?;
+                                client
+                                    .send_message(
+                                        command_source.channel_id.clone(),
+                                        match command_source.user {
+                                            Some(user) => {
+                                                format!("{}: current band conditions:", user.display_name)
+                                            }
+                                            None => "current band conditions:".to_string(),
+                                        },
+                                        /* tags = */ None,
+                                    )
+                                    .await?;
+
+                                for line in output {
+                                    client
+                                        .send_message(
+                                            command_source.channel_id.clone(),
+                                            line,
+                                            /* tags = */ None,
+                                        )
+                                        .await?;
+                                }
                             }
-                            None => "current band conditions:".to_string(),
-                        },
-                        /* tags = */ None,
-                    )
-                    .await?;
-
-                for line in output {
-                    client
-                        .send_message(
-                            command_source.channel_id.clone(),
-                            line,
-                            /* tags = */ None,
+                            FetchOutcome::Transient => {
+                                eprintln!("transient failure fetching solar data, backing off");
+                            }
+                            FetchOutcome::Unavailable => {
+                                client
+                                    .send_message(
+                                        command_source.channel_id.clone(),
+                                        "upstream unavailable".to_string(),
+                                        /* tags = */ None,
+                                    )
+                                    .await?;
+                            }
+                        }
+                    } else if command == "pota" {
+                        pota::handle_pota(
+                            &mut client,
+                            &watch_request_tx,
+                            &mut pota_upstream,
+                            &config,
+                            &arg,
+                            command_source,
                         )
                         .await?;
+                    } else if command == "grid" {
+                        grid::handle_grid(&mut client, &arg, command_source).await?;
+                    } else if command == "call" {
+                        callsign::handle_call(&mut client, &config, &arg, command_source).await?;
+                    } else if command == "dx" {
+                        dx::handle_dx(
+                            &mut client,
+                            dx_client.as_ref(),
+                            &config,
+                            &arg,
+                            command_source,
+                        )
+                        .await?;
+                    }
                 }
-            } else if command == "pota" {
-                handle_pota(&mut client, &arg, command_source).await?;
+            }
+            message = watch_message_rx.recv() => {
+                let Some(message) = message else {
+                    continue;
+                };
+
+                client
+                    .send_message(message.channel_id, message.text, /* tags = */ None)
+                    .await?;
             }
         }
     }
@@ -0,0 +1,336 @@
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::NaiveDateTime;
+use chrono::TimeDelta;
+use chrono::TimeZone;
+use chrono::Utc;
+use seabird::Client;
+use seabird::proto::ChannelSource;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::band::Band;
+use crate::band::Frequency;
+use crate::band::Mode;
+use crate::config::Config;
+use crate::reply::with_reply;
+use crate::resilience::FetchOutcome;
+use crate::resilience::Upstream;
+use crate::watch::WatchRequest;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedActivation {
+    activator: String,
+    name: String,
+    location_desc: String,
+    mode: Mode,
+    frequency: String,
+    spot_time: String,
+}
+
+impl ParsedActivation {
+    fn try_into_activation(self) -> Result<Activation> {
+        Ok(Activation {
+            activator: self.activator,
+            name: self.name,
+            location_desc: self.location_desc,
+            mode: self.mode,
+            frequency: ((self.frequency.parse::<f64>()? * 1_000.0).floor() as usize).into(),
+            spot_time: Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(
+                &self.spot_time,
+                "%Y-%m-%dT%H:%M:%S",
+            )?),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Activation {
+    pub activator: String,
+    pub name: String,
+    pub location_desc: String,
+    pub mode: Mode,
+    pub frequency: Frequency,
+    pub spot_time: DateTime<Utc>,
+}
+
+impl Activation {
+    fn age(&self) -> TimeDelta {
+        self.spot_time - Utc::now()
+    }
+
+    /// Whether this activation matches `band` (`None` meaning "any
+    /// supported band") and `mode`.
+    pub fn matches(&self, band: Option<&Band>, mode: &Mode) -> bool {
+        let on_band = match band {
+            Some(band) => band.frequency_range().contains(&self.frequency),
+            None => self.frequency.band().is_some(),
+        };
+
+        on_band && &self.mode == mode
+    }
+}
+
+pub async fn fetch_activations(url: &str) -> Result<Vec<Activation>> {
+    reqwest::get(url)
+        .await?
+        .json::<Vec<ParsedActivation>>()
+        .await?
+        .into_iter()
+        .map(|a| a.try_into_activation())
+        .collect::<Result<Vec<Activation>>>()
+}
+
+/// Notes appended to an activation's summary line when its reported mode
+/// doesn't match where it sits in the band plan, e.g. an "SSB" spot in the
+/// middle of a CW sub-band.
+fn mode_plausibility_note(activation: &Activation) -> Option<String> {
+    let band = activation.frequency.band()?;
+    if band.mode_is_plausible(&activation.frequency, &activation.mode) {
+        None
+    } else {
+        Some(format!(
+            " (note: {} is in the {} segment of {})",
+            activation.mode,
+            band.segment(&activation.frequency),
+            band
+        ))
+    }
+}
+
+pub fn format_activation(activation: &Activation) -> String {
+    let age_string = {
+        let seconds = activation.age().num_seconds().abs();
+        if seconds > 60 {
+            format!("{}m{}s", seconds / 60, seconds % 60)
+        } else {
+            seconds.to_string()
+        }
+    };
+
+    format!(
+        "[time:{},age:{}] {}MHz {}, {} - {} ({}){}",
+        activation.spot_time,
+        age_string,
+        activation.frequency,
+        activation.mode,
+        activation.location_desc,
+        activation.name,
+        activation.activator,
+        mode_plausibility_note(activation).unwrap_or_default(),
+    )
+}
+
+async fn handle_pota_impl(
+    client: &mut Client,
+    upstream: &mut Upstream,
+    config: &Config,
+    band_str: &str,
+    mode: Mode,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let band = if band_str.eq_ignore_ascii_case("all") {
+        None
+    } else {
+        match band_str.parse::<Band>() {
+            Ok(band) => Some(band),
+            Err(_) => {
+                client
+                    .send_message(
+                        command_source.channel_id.clone(),
+                        with_reply(&command_source, "invalid band".to_string()),
+                        /* tags = */ None,
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    match upstream.fetch(|| fetch_activations(&config.pota_url)).await {
+        FetchOutcome::Ready(activations) => {
+            let activation = activations
+                .into_iter()
+                .find(|activation| activation.matches(band.as_ref(), &mode));
+
+            match activation {
+                Some(activation) => {
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(&command_source, format_activation(&activation)),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                }
+                None => {
+                    let band_desc = band
+                        .as_ref()
+                        .map(|b| b.to_string())
+                        .unwrap_or_else(|| "any band".to_string());
+
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(
+                                &command_source,
+                                format!("no activations found on {} over {}", band_desc, mode),
+                            ),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                }
+            }
+        }
+        FetchOutcome::Transient => {
+            eprintln!("transient failure fetching POTA activations, backing off");
+        }
+        FetchOutcome::Unavailable => {
+            client
+                .send_message(
+                    command_source.channel_id.clone(),
+                    with_reply(&command_source, "upstream unavailable".to_string()),
+                    /* tags = */ None,
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_pota_watch(
+    client: &mut Client,
+    watch_requests: &mpsc::UnboundedSender<WatchRequest>,
+    band_str: &str,
+    mode: Mode,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let band = if band_str.eq_ignore_ascii_case("all") {
+        None
+    } else {
+        match band_str.parse::<Band>() {
+            Ok(band) => Some(band),
+            Err(_) => {
+                client
+                    .send_message(
+                        command_source.channel_id.clone(),
+                        with_reply(&command_source, "invalid band".to_string()),
+                        /* tags = */ None,
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let band_desc = band
+        .as_ref()
+        .map(|b| b.to_string())
+        .unwrap_or_else(|| "all bands".to_string());
+
+    // The receiving end lives in the watch loop; if it's gone the bot is
+    // shutting down, so there's nothing useful to do with the error.
+    let _ = watch_requests.send(WatchRequest {
+        channel_id: command_source.channel_id.clone(),
+        band,
+        mode: mode.clone(),
+    });
+
+    client
+        .send_message(
+            command_source.channel_id.clone(),
+            with_reply(
+                &command_source,
+                format!("watching {} over {} for new activations", band_desc, mode),
+            ),
+            /* tags = */ None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn handle_pota(
+    client: &mut Client,
+    watch_requests: &mpsc::UnboundedSender<WatchRequest>,
+    upstream: &mut Upstream,
+    config: &Config,
+    arg: &str,
+    command_source: ChannelSource,
+) -> Result<()> {
+    let parts: Vec<_> = arg.split_whitespace().collect();
+    match parts.as_slice() {
+        ["watch", band_str] => {
+            handle_pota_watch(
+                client,
+                watch_requests,
+                band_str,
+                config.default_mode.clone(),
+                command_source,
+            )
+            .await?;
+        }
+        ["watch", band_str, mode_str] => {
+            let mode = match mode_str.parse::<Mode>() {
+                Ok(mode) => mode,
+                Err(_) => {
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(&command_source, "invalid mode".to_string()),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            handle_pota_watch(client, watch_requests, band_str, mode, command_source).await?;
+        }
+        [band_str] => {
+            handle_pota_impl(
+                client,
+                upstream,
+                config,
+                band_str,
+                config.default_mode.clone(),
+                command_source,
+            )
+            .await?;
+        }
+        [band_str, mode_str] => {
+            let mode = match mode_str.parse::<Mode>() {
+                Ok(mode) => mode,
+                Err(_) => {
+                    client
+                        .send_message(
+                            command_source.channel_id.clone(),
+                            with_reply(&command_source, "invalid mode".to_string()),
+                            /* tags = */ None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            handle_pota_impl(client, upstream, config, band_str, mode, command_source).await?;
+        }
+        _ => {
+            client
+                .send_message(
+                    command_source.channel_id.clone(),
+                    with_reply(
+                        &command_source,
+                        "invalid pota command. Usage: pota <band|all> [mode] | pota watch <band|all> [mode]"
+                            .to_string(),
+                    ),
+                    /* tags = */ None,
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use seabird::proto::ChannelSource;
+
+/// Prefixes `message` with `@user: ` when the command that triggered it
+/// came from a known user, so replies read naturally in a shared channel.
+pub fn with_reply(command_source: &ChannelSource, message: String) -> String {
+    format!(
+        "{}{}",
+        command_source
+            .user
+            .as_ref()
+            .map(|u| format!("{}: ", u.display_name))
+            .unwrap_or_else(|| "".to_string()),
+        message
+    )
+}
@@ -0,0 +1,165 @@
+use std::future::Future;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Result;
+
+/// What happened when `Upstream::fetch` tried to run a fetch.
+pub enum FetchOutcome<T> {
+    /// The fetch succeeded (possibly after previous failures had us
+    /// backing off).
+    Ready(T),
+    /// The fetch failed (or was skipped because we're still backing off),
+    /// but no more than `max_errors_in_row` consecutive failures have been
+    /// seen. Callers should stay quiet rather than alarming anyone; a
+    /// later call tries the upstream again once the backoff has passed.
+    Transient,
+    /// More than `max_errors_in_row` consecutive failures have been seen,
+    /// whether this call failed again or was skipped inside the backoff
+    /// window. Callers should tell the user the upstream is down.
+    Unavailable,
+}
+
+/// Tracks consecutive failures talking to a single upstream so a caller
+/// can back off exponentially instead of hammering a downed service, and
+/// so a transient blip doesn't immediately get reported as an outage.
+pub struct Upstream {
+    max_errors_in_row: Option<usize>,
+    max_backoff: Duration,
+    consecutive_errors: usize,
+    next_attempt_at: Option<Instant>,
+}
+
+impl Upstream {
+    pub fn new(max_errors_in_row: Option<usize>, max_backoff: Duration) -> Self {
+        Self {
+            max_errors_in_row,
+            max_backoff,
+            consecutive_errors: 0,
+            next_attempt_at: None,
+        }
+    }
+
+    /// Runs `fetch`, unless we're still within a backoff window from a
+    /// previous failure, in which case it's skipped entirely.
+    pub async fn fetch<F, Fut, T>(&mut self, fetch: F) -> FetchOutcome<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some(next_attempt_at) = self.next_attempt_at {
+            if Instant::now() < next_attempt_at {
+                let exceeded = self
+                    .max_errors_in_row
+                    .is_some_and(|max| self.consecutive_errors > max);
+                return if exceeded {
+                    FetchOutcome::Unavailable
+                } else {
+                    FetchOutcome::Transient
+                };
+            }
+        }
+
+        match fetch().await {
+            Ok(value) => {
+                self.consecutive_errors = 0;
+                self.next_attempt_at = None;
+                FetchOutcome::Ready(value)
+            }
+            Err(_) => {
+                self.consecutive_errors += 1;
+                let backoff =
+                    Duration::from_secs(1 << self.consecutive_errors.min(20)).min(self.max_backoff);
+                self.next_attempt_at = Some(Instant::now() + backoff);
+
+                let exceeded = self
+                    .max_errors_in_row
+                    .is_some_and(|max| self.consecutive_errors > max);
+                if exceeded {
+                    FetchOutcome::Unavailable
+                } else {
+                    FetchOutcome::Transient
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn fetch_returns_ready_on_success() {
+        let mut upstream = Upstream::new(Some(5), Duration::from_secs(300));
+
+        let outcome = upstream.fetch(|| async { Ok(42) }).await;
+
+        assert!(matches!(outcome, FetchOutcome::Ready(42)));
+        assert_eq!(upstream.consecutive_errors, 0);
+        assert_eq!(upstream.next_attempt_at, None);
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_transient_below_the_error_threshold() {
+        let mut upstream = Upstream::new(Some(5), Duration::from_secs(300));
+
+        let outcome = upstream.fetch(|| async { Err::<(), _>(anyhow!("boom")) }).await;
+
+        assert!(matches!(outcome, FetchOutcome::Transient));
+        assert_eq!(upstream.consecutive_errors, 1);
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_unavailable_once_the_error_threshold_is_exceeded() {
+        // Pre-seed consecutive_errors past the first failure rather than
+        // waiting out the real backoff window between calls.
+        let mut upstream = Upstream {
+            max_errors_in_row: Some(1),
+            max_backoff: Duration::from_secs(300),
+            consecutive_errors: 1,
+            next_attempt_at: None,
+        };
+
+        let outcome = upstream.fetch(|| async { Err::<(), _>(anyhow!("boom")) }).await;
+
+        assert!(matches!(outcome, FetchOutcome::Unavailable));
+        assert_eq!(upstream.consecutive_errors, 2);
+    }
+
+    #[tokio::test]
+    async fn fetch_does_not_report_unavailable_inside_backoff_window_below_threshold() {
+        // Regression test: a single failure used to be reported as
+        // Unavailable on every call that landed inside the resulting
+        // backoff window, long before max_errors_in_row was exceeded.
+        let mut upstream = Upstream {
+            max_errors_in_row: Some(5),
+            max_backoff: Duration::from_secs(300),
+            consecutive_errors: 1,
+            next_attempt_at: Some(Instant::now() + Duration::from_secs(60)),
+        };
+
+        let outcome = upstream
+            .fetch(|| async { panic!("fetch should be skipped while backing off") })
+            .await;
+
+        assert!(matches!(outcome, FetchOutcome::Transient));
+    }
+
+    #[tokio::test]
+    async fn fetch_reports_unavailable_inside_backoff_window_once_threshold_exceeded() {
+        let mut upstream = Upstream {
+            max_errors_in_row: Some(1),
+            max_backoff: Duration::from_secs(300),
+            consecutive_errors: 2,
+            next_attempt_at: Some(Instant::now() + Duration::from_secs(60)),
+        };
+
+        let outcome = upstream
+            .fetch(|| async { panic!("fetch should be skipped while backing off") })
+            .await;
+
+        assert!(matches!(outcome, FetchOutcome::Unavailable));
+    }
+}
@@ -0,0 +1,332 @@
+use chrono::DateTime;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tokio::time::Instant;
+
+use crate::band::Band;
+use crate::band::Frequency;
+use crate::band::Mode;
+use crate::pota;
+use crate::pota::Activation;
+
+/// How often a subscription is re-polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(120);
+
+/// A new subscription joins an already-scheduled poll if one is due
+/// within this window, instead of always scheduling its own immediate
+/// poll. Keeps subscribers created moments apart from each forcing their
+/// own dedicated hit on api.pota.app.
+const JOIN_WINDOW: Duration = Duration::from_secs(10);
+
+/// A subscription that goes this long without turning up a new spot is
+/// dropped, on the assumption the channel it was created in has moved on.
+const EXPIRE_AFTER: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// A request to start watching a band/mode combination, sent from a
+/// `watch` command handler (POTA or DX cluster) to its background loop.
+pub struct WatchRequest {
+    pub channel_id: String,
+    pub band: Option<Band>,
+    pub mode: Mode,
+}
+
+/// A message the watch loop wants delivered to a channel.
+pub struct WatchMessage {
+    pub channel_id: String,
+    pub text: String,
+}
+
+struct Subscription {
+    channel_id: String,
+    band: Option<Band>,
+    mode: Mode,
+    seen: HashSet<(String, Frequency, DateTime<Utc>)>,
+    /// Whether `seen` has been primed by a first poll yet. Spots found
+    /// during priming are recorded but not alerted on, since they
+    /// predate the subscription rather than being genuinely new.
+    primed: bool,
+    last_activity: Instant,
+}
+
+/// Runs the background POTA spot watcher (SOTA spots are not polled). Receives new subscriptions on
+/// `requests` and emits alert text on `messages`; the caller is
+/// responsible for actually sending `messages` out to seabird, since this
+/// loop doesn't own a `Client`.
+///
+/// Subscriptions are tracked in a work queue keyed by next-poll time, so
+/// subscriptions that share a cadence are polled (and fetched) together
+/// instead of each issuing their own request to api.pota.app.
+pub async fn run(
+    mut requests: mpsc::UnboundedReceiver<WatchRequest>,
+    messages: mpsc::UnboundedSender<WatchMessage>,
+    pota_url: String,
+) {
+    let mut subscriptions: HashMap<u64, Subscription> = HashMap::new();
+    let mut next_id: u64 = 0;
+    let mut queue: BTreeMap<Instant, Vec<u64>> = BTreeMap::new();
+
+    loop {
+        let next_wake = queue.keys().next().copied();
+
+        tokio::select! {
+            request = requests.recv() => {
+                let Some(request) = request else {
+                    // Sender dropped; the bot is shutting down.
+                    return;
+                };
+
+                let id = next_id;
+                next_id += 1;
+
+                subscriptions.insert(
+                    id,
+                    Subscription {
+                        channel_id: request.channel_id,
+                        band: request.band,
+                        mode: request.mode,
+                        seen: HashSet::new(),
+                        primed: false,
+                        last_activity: Instant::now(),
+                    },
+                );
+
+                let now = Instant::now();
+                let bucket = join_bucket(&queue, now).unwrap_or(now);
+                queue.entry(bucket).or_default().push(id);
+            }
+            _ = sleep_until(next_wake) => {
+                let Some((&due, _)) = queue.iter().next() else {
+                    continue;
+                };
+                let ids = queue.remove(&due).unwrap_or_default();
+
+                poll_due(ids, &mut subscriptions, &mut queue, &messages, &pota_url).await;
+            }
+        }
+    }
+}
+
+/// Finds the nearest already-scheduled poll bucket within `JOIN_WINDOW`
+/// of `now`, so a freshly-created subscription piggybacks on it instead
+/// of getting its own dedicated poll.
+fn join_bucket(queue: &BTreeMap<Instant, Vec<u64>>, now: Instant) -> Option<Instant> {
+    let earliest = now.checked_sub(JOIN_WINDOW).unwrap_or(now);
+    let latest = now + JOIN_WINDOW;
+
+    queue
+        .range(earliest..=latest)
+        .map(|(&key, _)| key)
+        .min_by_key(|&key| key.max(now) - key.min(now))
+}
+
+async fn sleep_until(when: Option<Instant>) {
+    match when {
+        Some(when) => tokio::time::sleep_until(when).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn poll_due(
+    ids: Vec<u64>,
+    subscriptions: &mut HashMap<u64, Subscription>,
+    queue: &mut BTreeMap<Instant, Vec<u64>>,
+    messages: &mpsc::UnboundedSender<WatchMessage>,
+    pota_url: &str,
+) {
+    let activations = match pota::fetch_activations(pota_url).await {
+        Ok(activations) => activations,
+        Err(_) => {
+            // Upstream hiccup; leave the subscriptions as-is and retry on
+            // the next cadence rather than dropping them.
+            let now = Instant::now();
+            for id in ids {
+                queue.entry(now + POLL_INTERVAL).or_default().push(id);
+            }
+            return;
+        }
+    };
+
+    apply_activations(
+        Instant::now(),
+        ids,
+        &activations,
+        subscriptions,
+        queue,
+        messages,
+    );
+}
+
+/// Matches freshly-fetched `activations` against each subscription in
+/// `ids`, alerting on (and recording) genuinely new ones, then reschedules
+/// or expires each subscription. Split out from `poll_due` so the matching
+/// logic can be unit tested without going over the network.
+fn apply_activations(
+    now: Instant,
+    ids: Vec<u64>,
+    activations: &[Activation],
+    subscriptions: &mut HashMap<u64, Subscription>,
+    queue: &mut BTreeMap<Instant, Vec<u64>>,
+    messages: &mpsc::UnboundedSender<WatchMessage>,
+) {
+    for id in ids {
+        let Some(subscription) = subscriptions.get_mut(&id) else {
+            continue;
+        };
+
+        for activation in activations {
+            if !activation.matches(subscription.band.as_ref(), &subscription.mode) {
+                continue;
+            }
+
+            let key = (
+                activation.activator.clone(),
+                activation.frequency.clone(),
+                activation.spot_time,
+            );
+            let is_new = subscription.seen.insert(key);
+            if is_new && subscription.primed {
+                subscription.last_activity = Instant::now();
+                let _ = messages.send(WatchMessage {
+                    channel_id: subscription.channel_id.clone(),
+                    text: format!("new spot: {}", pota::format_activation(activation)),
+                });
+            }
+        }
+        subscription.primed = true;
+
+        if subscription.last_activity.elapsed() > EXPIRE_AFTER {
+            subscriptions.remove(&id);
+        } else {
+            queue.entry(now + POLL_INTERVAL).or_default().push(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activation(activator: &str, frequency: usize) -> Activation {
+        Activation {
+            activator: activator.to_string(),
+            name: "Test Park".to_string(),
+            location_desc: "US-XX".to_string(),
+            mode: Mode::Ssb,
+            frequency: frequency.into(),
+            // Fixed, so the same activator/frequency re-sent in a later
+            // poll is recognised as already seen.
+            spot_time: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    fn subscription() -> Subscription {
+        Subscription {
+            channel_id: "#test".to_string(),
+            band: None,
+            mode: Mode::Ssb,
+            seen: HashSet::new(),
+            primed: false,
+            last_activity: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn join_bucket_returns_none_when_queue_is_empty() {
+        let queue: BTreeMap<Instant, Vec<u64>> = BTreeMap::new();
+        assert!(join_bucket(&queue, Instant::now()).is_none());
+    }
+
+    #[test]
+    fn join_bucket_joins_a_poll_due_soon() {
+        let now = Instant::now();
+        let due = now + Duration::from_secs(5);
+        let mut queue = BTreeMap::new();
+        queue.insert(due, vec![1]);
+
+        assert_eq!(join_bucket(&queue, now), Some(due));
+    }
+
+    #[test]
+    fn join_bucket_ignores_a_poll_outside_the_window() {
+        let now = Instant::now();
+        let due = now + JOIN_WINDOW + Duration::from_secs(1);
+        let mut queue = BTreeMap::new();
+        queue.insert(due, vec![1]);
+
+        assert!(join_bucket(&queue, now).is_none());
+    }
+
+    #[test]
+    fn join_bucket_picks_the_nearest_of_several_candidates() {
+        let now = Instant::now();
+        let near = now + Duration::from_secs(2);
+        let far = now + Duration::from_secs(8);
+        let mut queue = BTreeMap::new();
+        queue.insert(far, vec![1]);
+        queue.insert(near, vec![2]);
+
+        assert_eq!(join_bucket(&queue, now), Some(near));
+    }
+
+    #[test]
+    fn apply_activations_primes_seen_without_alerting_on_first_poll() {
+        let mut subscriptions = HashMap::new();
+        subscriptions.insert(1u64, subscription());
+        let mut queue = BTreeMap::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let activations = vec![activation("W1AW", 14_074_000)];
+        apply_activations(
+            Instant::now(),
+            vec![1u64],
+            &activations,
+            &mut subscriptions,
+            &mut queue,
+            &tx,
+        );
+
+        assert!(rx.try_recv().is_err());
+        assert!(subscriptions[&1u64].primed);
+        assert_eq!(subscriptions[&1u64].seen.len(), 1);
+    }
+
+    #[test]
+    fn apply_activations_alerts_on_a_genuinely_new_spot_after_priming() {
+        let mut subscriptions = HashMap::new();
+        subscriptions.insert(1u64, subscription());
+        let mut queue = BTreeMap::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let first_poll = vec![activation("W1AW", 14_074_000)];
+        apply_activations(
+            Instant::now(),
+            vec![1u64],
+            &first_poll,
+            &mut subscriptions,
+            &mut queue,
+            &tx,
+        );
+        assert!(rx.try_recv().is_err());
+
+        let second_poll = vec![
+            activation("W1AW", 14_074_000),
+            activation("K1ABC", 7_074_000),
+        ];
+        apply_activations(
+            Instant::now(),
+            vec![1u64],
+            &second_poll,
+            &mut subscriptions,
+            &mut queue,
+            &tx,
+        );
+
+        let message = rx.try_recv().expect("new spot should alert");
+        assert!(message.text.contains("K1ABC"));
+        assert!(rx.try_recv().is_err());
+    }
+}